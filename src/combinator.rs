@@ -0,0 +1,103 @@
+use crate::de::{Decode, Decoder};
+use crate::err::{Error, Result};
+use crate::parse::BenDecoder;
+
+/// Asserts the next value is a UTF-8 byte string, erroring otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Text<'buf>(pub &'buf str);
+
+impl<'buf, C> Decode<'buf, C> for Text<'buf> {
+    fn decode<D>(decoder: D, ctx: &mut C) -> Result<Self>
+    where
+        D: Decoder<'buf, C>,
+    {
+        <&'buf str>::decode(decoder, ctx).map(Text)
+    }
+}
+
+/// Asserts the next value is a raw byte string, erroring otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Binary<'buf>(pub &'buf [u8]);
+
+impl<'buf, C> Decode<'buf, C> for Binary<'buf> {
+    fn decode<D>(decoder: D, ctx: &mut C) -> Result<Self>
+    where
+        D: Decoder<'buf, C>,
+    {
+        <&'buf [u8]>::decode(decoder, ctx).map(Binary)
+    }
+}
+
+macro_rules! one_of_impl {
+    ($name:ident, $( $t:ident ),+) => {
+        /// Decodes as whichever variant parses successfully, trying them
+        /// in declaration order. Each attempt runs against a fresh
+        /// decoder over a snapshot of the next value's raw bytes, so a
+        /// failed alternative leaves the next one exactly where it
+        /// started instead of having consumed part of the input.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum $name<$( $t ),+> {
+            $( $t($t) ),+
+        }
+
+        impl<'buf, Ctx, $( $t ),+> Decode<'buf, Ctx> for $name<$( $t ),+>
+        where
+            $( $t: Decode<'buf, Ctx> ),+
+        {
+            fn decode<Dec>(decoder: Dec, ctx: &mut Ctx) -> Result<Self>
+            where
+                Dec: Decoder<'buf, Ctx>,
+            {
+                let raw = decoder.decode_raw(ctx)?;
+
+                $(
+                    if let Ok(v) = $t::decode(&mut BenDecoder::new(raw), ctx) {
+                        return Ok($name::$t(v));
+                    }
+                )+
+
+                Err(Error::Type {
+                    reason: "No alternative matched",
+                })
+            }
+        }
+    };
+}
+
+one_of_impl!(OneOf2, A, B);
+one_of_impl!(OneOf3, A, B, C);
+one_of_impl!(OneOf4, A, B, C, D);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn picks_first_matching_variant_in_declaration_order() {
+        let v: OneOf2<i64, &[u8]> = parse(b"i5e").unwrap();
+        assert!(matches!(v, OneOf2::A(5)));
+    }
+
+    #[test]
+    fn falls_back_to_a_later_variant() {
+        let v: OneOf2<i64, &[u8]> = parse(b"3:abc").unwrap();
+        assert!(matches!(v, OneOf2::B(b"abc")));
+    }
+
+    #[test]
+    fn failed_alternative_does_not_consume_input() {
+        // Each list element independently retries from variant A, so a
+        // failed attempt on one element must leave `pos` exactly where it
+        // started or the next element would desync.
+        let v: Vec<OneOf2<i64, &[u8]>> = parse(b"l3:abci5ee").unwrap();
+        assert!(matches!(v[0], OneOf2::B(b"abc")));
+        assert!(matches!(v[1], OneOf2::A(5)));
+    }
+
+    #[test]
+    fn errors_when_no_alternative_matches() {
+        let res: Result<OneOf2<i64, i64>> = parse(b"3:abc");
+        assert!(res.is_err());
+    }
+}
@@ -1,40 +1,63 @@
 use crate::err::{Error, Result};
 
-pub trait Decode<'buf>: Sized {
-    fn decode<D>(decoder: D) -> Result<Self>
+/// `C` is a caller-supplied decoding context, threaded through every impl so
+/// callers can carry state across a whole decode (e.g. a max nesting depth or
+/// allocation budget while decoding untrusted data). Defaults to `()` for
+/// impls that don't need one.
+pub trait Decode<'buf, C = ()>: Sized {
+    fn decode<D>(decoder: D, ctx: &mut C) -> Result<Self>
     where
-        D: Decoder<'buf>;
+        D: Decoder<'buf, C>;
+
+    /// The value to use when this type is expected but absent, e.g. a
+    /// missing dictionary entry or a list that ran out of elements early.
+    /// Returning `None` (the default) means absence is an error.
+    fn nil() -> Option<Self> {
+        None
+    }
 }
 
-pub trait Decoder<'buf> {
-    fn decode_dict<V>(self, visitor: V) -> Result<V::Value>
+pub trait Decoder<'buf, C> {
+    fn decode_dict<V>(self, visitor: V, ctx: &mut C) -> Result<V::Value>
     where
-        V: Visitor<'buf>;
+        V: Visitor<'buf, C>;
 
-    fn decode_list<V>(self, visitor: V) -> Result<V::Value>
+    fn decode_list<V>(self, visitor: V, ctx: &mut C) -> Result<V::Value>
     where
-        V: Visitor<'buf>;
+        V: Visitor<'buf, C>;
 
-    fn decode_int(self) -> Result<i64>;
+    fn decode_int(self, ctx: &mut C) -> Result<i64>;
 
-    fn decode_bytes(self) -> Result<&'buf [u8]>;
+    fn decode_bytes(self, ctx: &mut C) -> Result<&'buf [u8]>;
+
+    /// Decodes whatever value comes next, dispatching to the matching
+    /// `visit_*` method based on the leading tag byte instead of requiring
+    /// the caller to know the shape ahead of time.
+    fn decode_any<V>(self, visitor: V, ctx: &mut C) -> Result<V::Value>
+    where
+        V: Visitor<'buf, C>;
+
+    /// Returns the exact raw bytes of the next value, without interpreting
+    /// them. Used to capture e.g. a sub-dictionary verbatim so it can be
+    /// hashed as it appeared on the wire (a BitTorrent info-hash).
+    fn decode_raw(self, ctx: &mut C) -> Result<&'buf [u8]>;
 }
 
-pub trait Visitor<'buf>: Sized {
+pub trait Visitor<'buf, C>: Sized {
     type Value;
 
-    fn visit_dict<A>(self, _v: A) -> Result<Self::Value>
+    fn visit_dict<A>(self, _v: A, _ctx: &mut C) -> Result<Self::Value>
     where
-        A: Dict<'buf>,
+        A: Dict<'buf, C>,
     {
         Err(Error::Type {
             reason: "Dict not expected",
         })
     }
 
-    fn visit_list<A>(self, _v: A) -> Result<Self::Value>
+    fn visit_list<A>(self, _v: A, _ctx: &mut C) -> Result<Self::Value>
     where
-        A: List<'buf>,
+        A: List<'buf, C>,
     {
         Err(Error::Type {
             reason: "List not expected",
@@ -54,14 +77,39 @@ pub trait Visitor<'buf>: Sized {
     }
 }
 
-pub trait Dict<'buf> {
-    fn next_entry<T>(&mut self) -> Result<Option<(&'buf [u8], T)>>
+pub trait Dict<'buf, C> {
+    fn next_entry<T>(&mut self, ctx: &mut C) -> Result<Option<(&'buf [u8], T)>>
+    where
+        T: Decode<'buf, C>;
+
+    /// Scans forward from the current position for an entry whose key
+    /// matches `key`, skipping over (and discarding) any non-matching
+    /// entries it passes along the way. Returns `None` once the dict ends
+    /// without a match. This is how an optional field is decoded as `None`
+    /// instead of forcing positional consumption. Entries passed over are
+    /// not revisited, so keys must be looked up in the order they appear in
+    /// the dict; a key that lies before the current position is not found.
+    fn get<T>(&mut self, key: &'buf [u8], ctx: &mut C) -> Result<Option<T>>
     where
-        T: Decode<'buf>;
+        T: Decode<'buf, C>;
 }
 
-pub trait List<'buf> {
-    fn next_element<T>(&mut self) -> Result<Option<T>>
+pub trait List<'buf, C> {
+    fn next_element<T>(&mut self, ctx: &mut C) -> Result<Option<T>>
     where
-        T: Decode<'buf>;
+        T: Decode<'buf, C>;
+}
+
+/// The exact raw bencoded bytes of a single value, captured without being
+/// interpreted. See [`Decoder::decode_raw`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Raw<'buf>(pub &'buf [u8]);
+
+impl<'buf, C> Decode<'buf, C> for Raw<'buf> {
+    fn decode<D>(decoder: D, ctx: &mut C) -> Result<Self>
+    where
+        D: Decoder<'buf, C>,
+    {
+        decoder.decode_raw(ctx).map(Raw)
+    }
 }
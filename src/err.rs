@@ -9,6 +9,8 @@ pub enum Error {
     Parse { reason: &'static str, pos: usize },
     Unexpected { pos: usize },
     Overflow { pos: usize },
+    Io(std::io::Error),
+    NonCanonical { reason: &'static str, pos: usize },
 }
 
 impl std::error::Error for Error {}
@@ -26,6 +28,10 @@ impl fmt::Display for Error {
             Error::Parse { reason, pos } => write!(f, "Parse Error at {}: {}", pos, reason),
             Error::Unexpected { pos } => write!(f, "Unexpected character at {}", pos),
             Error::Overflow { pos } => write!(f, "Numeric overflow occurred at {}", pos),
+            Error::Io(err) => write!(f, "I/O error: {}", err),
+            Error::NonCanonical { reason, pos } => {
+                write!(f, "Non-canonical bencode at {}: {}", pos, reason)
+            }
         }
     }
 }
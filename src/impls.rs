@@ -4,71 +4,90 @@ use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::hash::Hash;
 use std::marker::PhantomData;
 
-impl<'buf> Decode<'buf> for &'buf [u8] {
-    fn decode<D>(decoder: D) -> Result<Self>
+impl<'buf, C> Decode<'buf, C> for &'buf [u8] {
+    fn decode<D>(decoder: D, ctx: &mut C) -> Result<Self>
     where
-        D: Decoder<'buf>,
+        D: Decoder<'buf, C>,
     {
-        decoder.decode_bytes()
+        decoder.decode_bytes(ctx)
     }
 }
 
-impl<'buf> Decode<'buf> for i64 {
-    fn decode<D>(decoder: D) -> Result<Self>
+impl<'buf, C> Decode<'buf, C> for i64 {
+    fn decode<D>(decoder: D, ctx: &mut C) -> Result<Self>
     where
-        D: Decoder<'buf>,
+        D: Decoder<'buf, C>,
     {
-        decoder.decode_int()
+        decoder.decode_int(ctx)
     }
 }
 
-impl<'buf> Decode<'buf> for &'buf str {
-    fn decode<D>(decoder: D) -> Result<Self>
+impl<'buf, C> Decode<'buf, C> for &'buf str {
+    fn decode<D>(decoder: D, ctx: &mut C) -> Result<Self>
     where
-        D: Decoder<'buf>,
+        D: Decoder<'buf, C>,
     {
-        let bytes = decoder.decode_bytes()?;
+        let bytes = decoder.decode_bytes(ctx)?;
         std::str::from_utf8(bytes).map_err(|_| Error::Type {
             reason: "Not a valid UTF-8 string",
         })
     }
 }
 
+impl<'buf, C, T> Decode<'buf, C> for Option<T>
+where
+    T: Decode<'buf, C>,
+{
+    fn decode<D>(decoder: D, ctx: &mut C) -> Result<Self>
+    where
+        D: Decoder<'buf, C>,
+    {
+        Ok(Some(T::decode(decoder, ctx)?))
+    }
+
+    fn nil() -> Option<Self> {
+        Some(None)
+    }
+}
+
 ////////////////// Impls //////////////////
 
 macro_rules! tuple_impl {
     ($($t:ident),* ) => {
-        impl<'buf, $( $t ),*> Decode<'buf> for ($( $t ),*)
+        impl<'buf, C, $( $t ),*> Decode<'buf, C> for ($( $t ),*)
         where
-            $( $t: Decode<'buf> ),*
+            $( $t: Decode<'buf, C> ),*
         {
-            fn decode<D>(decoder: D) -> Result<Self>
+            fn decode<D>(decoder: D, ctx: &mut C) -> Result<Self>
             where
-                D: Decoder<'buf>,
+                D: Decoder<'buf, C>,
             {
                 struct TheVisitor<$( $t ),*>(PhantomData<($( $t ),*)>);
 
-                impl<'buf, $( $t ),*> Visitor<'buf> for TheVisitor<$( $t ),*>
+                impl<'buf, C, $( $t ),*> Visitor<'buf, C> for TheVisitor<$( $t ),*>
                 where
-                    $( $t: Decode<'buf> ),*
+                    $( $t: Decode<'buf, C> ),*
                 {
                     type Value = ($( $t ),*);
 
                     #[allow(unused)]
-                    fn visit_list<A>(self, mut list: A) -> Result<Self::Value>
+                    fn visit_list<A>(self, mut list: A, ctx: &mut C) -> Result<Self::Value>
                     where
-                        A: List<'buf>
+                        A: List<'buf, C>
                     {
                         Ok(($(
-                            match list.next_element::<$t>()? {
+                            match list.next_element::<$t>(ctx)? {
                                 Some(t) => t,
-                                None => return Err(Error::Eof),
+                                None => match $t::nil() {
+                                    Some(t) => t,
+                                    None => return Err(Error::Eof),
+                                },
                             }
                         ),*))
                     }
                 }
 
-                decoder.decode_list(TheVisitor(PhantomData))
+                decoder.decode_list(TheVisitor(PhantomData), ctx)
             }
         }
     }
@@ -93,36 +112,39 @@ tuple_impl!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15
 
 macro_rules! array_impl {
     ($len:literal => [$( $n:tt ),+]) => {
-        impl<'buf, T> Decode<'buf> for [T; $len]
+        impl<'buf, C, T> Decode<'buf, C> for [T; $len]
         where
-            T: Decode<'buf>,
+            T: Decode<'buf, C>,
         {
-            fn decode<D>(decoder: D) -> Result<Self>
+            fn decode<D>(decoder: D, ctx: &mut C) -> Result<Self>
             where
-                D: Decoder<'buf>,
+                D: Decoder<'buf, C>,
             {
                 struct TheVisitor<T>(PhantomData<T>);
 
-                impl<'buf, T> Visitor<'buf> for TheVisitor<T>
+                impl<'buf, C, T> Visitor<'buf, C> for TheVisitor<T>
                 where
-                    T: Decode<'buf>,
+                    T: Decode<'buf, C>,
                 {
                     type Value = [T; $len];
 
-                    fn visit_list<A>(self, mut list: A) -> Result<Self::Value>
+                    fn visit_list<A>(self, mut list: A, ctx: &mut C) -> Result<Self::Value>
                     where
-                        A: List<'buf>
+                        A: List<'buf, C>
                     {
                         Ok([$(
-                            match list.next_element()? {
+                            match list.next_element(ctx)? {
                                 Some(t) => t,
-                                None => return Err(Error::Length { expected: $len, actual: $n }),
+                                None => match T::nil() {
+                                    Some(t) => t,
+                                    None => return Err(Error::Length { expected: $len, actual: $n }),
+                                },
                             }
                         ),+])
                     }
                 }
 
-                decoder.decode_list(TheVisitor(PhantomData))
+                decoder.decode_list(TheVisitor(PhantomData), ctx)
             }
         }
     }
@@ -162,77 +184,77 @@ array_impl!(31 => [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17,
 array_impl!(32 => [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31]);
 
 macro_rules! list_impl {
-    ($ty:ident, $fn:ident, Decode<'buf> $(+ $bounds:ident )* ) => {
-        impl<'buf, T> Decode<'buf> for $ty<T>
+    ($ty:ident, $fn:ident, Decode<'buf, C> $(+ $bounds:ident )* ) => {
+        impl<'buf, C, T> Decode<'buf, C> for $ty<T>
         where
-            T: Decode<'buf> $( + $bounds )*,
+            T: Decode<'buf, C> $( + $bounds )*,
         {
-            fn decode<D>(decoder: D) -> Result<Self>
+            fn decode<D>(decoder: D, ctx: &mut C) -> Result<Self>
             where
-                D: Decoder<'buf>,
+                D: Decoder<'buf, C>,
             {
                 struct TheVisitor<T>(PhantomData<T>);
 
-                impl<'buf, T> Visitor<'buf> for TheVisitor<T>
+                impl<'buf, C, T> Visitor<'buf, C> for TheVisitor<T>
                 where
-                    T: Decode<'buf> $( + $bounds )*,
+                    T: Decode<'buf, C> $( + $bounds )*,
                 {
                     type Value = $ty<T>;
 
-                    fn visit_list<A>(self, mut list: A) -> Result<Self::Value>
+                    fn visit_list<A>(self, mut list: A, ctx: &mut C) -> Result<Self::Value>
                     where
-                        A: List<'buf>
+                        A: List<'buf, C>
                     {
                         let mut out = $ty::new();
-                        while let Some(t) = list.next_element()? {
+                        while let Some(t) = list.next_element(ctx)? {
                             out.$fn(t);
                         }
                         Ok(out)
                     }
                 }
 
-                decoder.decode_list(TheVisitor(PhantomData))
+                decoder.decode_list(TheVisitor(PhantomData), ctx)
             }
         }
     }
 }
 
-list_impl!(Vec, push, Decode<'buf>);
-list_impl!(VecDeque, push_back, Decode<'buf>);
-list_impl!(HashSet, insert, Decode<'buf> + Hash + Eq);
-list_impl!(BTreeSet, insert, Decode<'buf> + Ord);
+list_impl!(Vec, push, Decode<'buf, C>);
+list_impl!(VecDeque, push_back, Decode<'buf, C>);
+list_impl!(HashSet, insert, Decode<'buf, C> + Hash + Eq);
+list_impl!(BTreeSet, insert, Decode<'buf, C> + Ord);
 
 macro_rules! map_impl {
     ($ty:ident) => {
-        impl<'buf, T> Decode<'buf> for $ty<&'buf [u8], T>
+        impl<'buf, C, T> Decode<'buf, C> for $ty<&'buf [u8], T>
         where
-            T: Decode<'buf>,
+            T: Decode<'buf, C>,
         {
-            fn decode<D>(decoder: D) -> Result<Self>
+            fn decode<D>(decoder: D, ctx: &mut C) -> Result<Self>
             where
-                D: Decoder<'buf>,
+                D: Decoder<'buf, C>,
             {
                 struct TheVisitor<T>(PhantomData<T>);
 
-                impl<'buf, T> Visitor<'buf> for TheVisitor<T>
+                impl<'buf, C, T> Visitor<'buf, C> for TheVisitor<T>
                 where
-                    T: Decode<'buf>,
+                    T: Decode<'buf, C>,
                 {
                     type Value = $ty<&'buf [u8], T>;
 
-                    fn visit_dict<A>(self, mut dict: A) -> Result<Self::Value>
+                    fn visit_dict<A>(self, mut dict: A, ctx: &mut C) -> Result<Self::Value>
                     where
-                        A: Dict<'buf>,
+                        A: Dict<'buf, C>,
                     {
                         let mut out = $ty::new();
-                        while let Some((k, v)) = dict.next_entry()? {
+                        while let Some((k, v)) = dict.next_entry(ctx)? {
                             out.insert(k, v);
                         }
                         Ok(out)
                     }
                 }
 
-                decoder.decode_list(TheVisitor(PhantomData))
+                decoder.decode_dict(TheVisitor(PhantomData), ctx)
             }
         }
     };
@@ -13,21 +13,21 @@ struct Foo<'a> {
 }
 
 impl<'a> Decode<'a> for Foo<'a> {
-    fn decode<D>(decoder: D) -> Result<Self>
+    fn decode<D>(decoder: D, ctx: &mut ()) -> Result<Self>
     where
-        D: Decoder<'a>,
+        D: Decoder<'a, ()>,
     {
         struct FooVisitor;
 
-        impl<'buf> Visitor<'buf> for FooVisitor {
+        impl<'buf> Visitor<'buf, ()> for FooVisitor {
             type Value = Foo<'buf>;
 
-            fn visit_dict<A>(self, mut dict: A) -> Result<Self::Value>
+            fn visit_dict<A>(self, mut dict: A, ctx: &mut ()) -> Result<Self::Value>
             where
-                A: Dict<'buf>,
+                A: Dict<'buf, ()>,
             {
-                if let Some((b"a", id)) = dict.next_entry()? {
-                    if let Some((b"b", b)) = dict.next_entry()? {
+                if let Some((b"a", id)) = dict.next_entry(ctx)? {
+                    if let Some((b"b", b)) = dict.next_entry(ctx)? {
                         return Ok(Foo { id, b });
                     }
                 }
@@ -35,6 +35,6 @@ impl<'a> Decode<'a> for Foo<'a> {
             }
         }
 
-        decoder.decode_dict(FooVisitor)
+        decoder.decode_dict(FooVisitor, ctx)
     }
 }
@@ -1,15 +1,18 @@
+use std::marker::PhantomData;
+
 use crate::de::{Decode, Decoder, Dict, List, Visitor};
 use crate::err::{Error, Result};
 
 pub struct BenDecoder<'buf> {
     buf: &'buf [u8],
     pos: usize,
+    strict: bool,
 }
 
-impl<'buf> Decoder<'buf> for &mut BenDecoder<'buf> {
-    fn decode_dict<V>(self, visitor: V) -> Result<V::Value>
+impl<'buf, C> Decoder<'buf, C> for &mut BenDecoder<'buf> {
+    fn decode_dict<V>(self, visitor: V, ctx: &mut C) -> Result<V::Value>
     where
-        V: Visitor<'buf>,
+        V: Visitor<'buf, C>,
     {
         if self.next_char()? != b'd' {
             return Err(Error::Parse {
@@ -18,7 +21,11 @@ impl<'buf> Decoder<'buf> for &mut BenDecoder<'buf> {
             });
         }
 
-        let out = visitor.visit_dict(&mut *self)?;
+        let mut cursor = DictCursor {
+            dec: &mut *self,
+            last_key: None,
+        };
+        let out = visitor.visit_dict(&mut cursor, ctx)?;
         if self.next_char()? == b'e' {
             Ok(out)
         } else {
@@ -26,9 +33,9 @@ impl<'buf> Decoder<'buf> for &mut BenDecoder<'buf> {
         }
     }
 
-    fn decode_list<V>(self, visitor: V) -> Result<V::Value>
+    fn decode_list<V>(self, visitor: V, ctx: &mut C) -> Result<V::Value>
     where
-        V: Visitor<'buf>,
+        V: Visitor<'buf, C>,
     {
         if self.next_char()? != b'l' {
             return Err(Error::Parse {
@@ -37,7 +44,7 @@ impl<'buf> Decoder<'buf> for &mut BenDecoder<'buf> {
             });
         }
 
-        let out = visitor.visit_list(&mut *self)?;
+        let out = visitor.visit_list(&mut *self, ctx)?;
 
         if self.next_char()? == b'e' {
             Ok(out)
@@ -46,7 +53,7 @@ impl<'buf> Decoder<'buf> for &mut BenDecoder<'buf> {
         }
     }
 
-    fn decode_int(self) -> Result<i64> {
+    fn decode_int(self, _ctx: &mut C) -> Result<i64> {
         if self.next_char()? != b'i' {
             return Err(Error::Parse {
                 reason: "Expected integer",
@@ -57,7 +64,7 @@ impl<'buf> Decoder<'buf> for &mut BenDecoder<'buf> {
         self.parse_i64(b'e')
     }
 
-    fn decode_bytes(self) -> Result<&'buf [u8]> {
+    fn decode_bytes(self, _ctx: &mut C) -> Result<&'buf [u8]> {
         if let b'0'..=b'9' = self.peek_char()? {
             // Ok
         } else {
@@ -77,11 +84,95 @@ impl<'buf> Decoder<'buf> for &mut BenDecoder<'buf> {
             None => Err(Error::Eof),
         }
     }
+
+    fn decode_any<V>(self, visitor: V, ctx: &mut C) -> Result<V::Value>
+    where
+        V: Visitor<'buf, C>,
+    {
+        match self.peek_char()? {
+            b'd' => self.decode_dict(visitor, ctx),
+            b'l' => self.decode_list(visitor, ctx),
+            b'i' => {
+                let v = self.decode_int(ctx)?;
+                visitor.visit_int(v)
+            }
+            b'0'..=b'9' => {
+                let v = self.decode_bytes(ctx)?;
+                visitor.visit_bytes(v)
+            }
+            _ => Err(Error::Unexpected { pos: self.pos }),
+        }
+    }
+
+    fn decode_raw(self, ctx: &mut C) -> Result<&'buf [u8]> {
+        let start = self.pos;
+        self.skip_value(ctx)?;
+        Ok(&self.buf[start..self.pos])
+    }
 }
 
 impl<'buf> BenDecoder<'buf> {
     pub fn new(buf: &'buf [u8]) -> Self {
-        Self { buf, pos: 0 }
+        Self {
+            buf,
+            pos: 0,
+            strict: false,
+        }
+    }
+
+    /// Like [`BenDecoder::new`], but rejects non-canonical bencode: integers
+    /// and byte-string lengths with leading zeros, negative zero, and
+    /// dictionaries whose keys aren't in strictly ascending order.
+    pub fn new_strict(buf: &'buf [u8]) -> Self {
+        Self {
+            buf,
+            pos: 0,
+            strict: true,
+        }
+    }
+
+    /// Decodes successive top-level values one at a time, stopping once the
+    /// buffer is fully consumed, instead of requiring the whole input to be
+    /// a single document.
+    pub fn values<T>(&mut self) -> Values<'_, 'buf, T>
+    where
+        T: Decode<'buf>,
+    {
+        Values {
+            decoder: self,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Advances `pos` past exactly one value, without interpreting it.
+    fn skip_value<C>(&mut self, ctx: &mut C) -> Result<()> {
+        match self.peek_char()? {
+            b'i' => {
+                self.decode_int(ctx)?;
+            }
+            b'0'..=b'9' => {
+                self.decode_bytes(ctx)?;
+            }
+            b'l' => {
+                self.next_char()?;
+                while self.peek_char()? != b'e' {
+                    self.skip_value(ctx)?;
+                }
+                self.next_char()?;
+            }
+            b'd' => {
+                self.next_char()?;
+                let mut last_key = None;
+                while self.peek_char()? != b'e' {
+                    let key = self.decode_bytes(ctx)?;
+                    check_key_order(self.strict, &mut last_key, key, self.pos)?;
+                    self.skip_value(ctx)?;
+                }
+                self.next_char()?;
+            }
+            _ => return Err(Error::Unexpected { pos: self.pos }),
+        }
+        Ok(())
     }
 
     fn peek_char(&mut self) -> Result<u8> {
@@ -95,6 +186,8 @@ impl<'buf> BenDecoder<'buf> {
     }
 
     fn parse_usize(&mut self, stop_char: u8) -> Result<usize> {
+        let start = self.pos;
+
         if let b'0'..=b'9' = self.peek_char()? {
             // Ok
         } else {
@@ -102,16 +195,26 @@ impl<'buf> BenDecoder<'buf> {
         }
 
         let mut val: usize = 0;
+        let mut digits = 0usize;
         loop {
             match self.next_char()? {
                 c @ b'0'..=b'9' => {
+                    digits += 1;
                     let digit = (c - b'0') as usize;
                     match val.checked_mul(10).and_then(|n| n.checked_add(digit)) {
                         Some(n) => val = n,
                         None => return Err(Error::Overflow { pos: self.pos }),
                     }
                 }
-                c if c == stop_char => return Ok(val),
+                c if c == stop_char => {
+                    if self.strict && digits > 1 && self.buf[start] == b'0' {
+                        return Err(Error::NonCanonical {
+                            reason: "length has a leading zero",
+                            pos: start,
+                        });
+                    }
+                    return Ok(val);
+                }
                 _ => return Err(Error::Unexpected { pos: self.pos }),
             }
         }
@@ -125,6 +228,8 @@ impl<'buf> BenDecoder<'buf> {
             negative = true;
         }
 
+        let start = self.pos;
+
         if let b'0'..=b'9' = self.peek_char()? {
             // Ok
         } else {
@@ -132,9 +237,11 @@ impl<'buf> BenDecoder<'buf> {
         }
 
         let mut val: i64 = 0;
+        let mut digits = 0usize;
         loop {
             match self.next_char()? {
                 c @ b'0'..=b'9' => {
+                    digits += 1;
                     let digit = (c - b'0') as i64;
                     match val.checked_mul(10).and_then(|n| n.checked_add(digit)) {
                         Some(n) => val = n,
@@ -142,6 +249,21 @@ impl<'buf> BenDecoder<'buf> {
                     }
                 }
                 c if c == stop_char => {
+                    if self.strict {
+                        if digits > 1 && self.buf[start] == b'0' {
+                            return Err(Error::NonCanonical {
+                                reason: "integer has a leading zero",
+                                pos: start,
+                            });
+                        }
+                        if negative && val == 0 {
+                            return Err(Error::NonCanonical {
+                                reason: "negative zero is not canonical",
+                                pos: start,
+                            });
+                        }
+                    }
+
                     if negative {
                         val *= -1;
                     }
@@ -153,31 +275,217 @@ impl<'buf> BenDecoder<'buf> {
     }
 }
 
-impl<'buf> Dict<'buf> for &mut BenDecoder<'buf> {
-    fn next_entry<T>(&mut self) -> Result<Option<(&'buf [u8], T)>>
+/// Tracks the previously-seen key of the dict currently being decoded, so
+/// strict mode can reject keys that aren't in ascending order. Scoped to a
+/// single `decode_dict` call so nested dicts don't interfere with each
+/// other's ordering.
+struct DictCursor<'a, 'buf> {
+    dec: &'a mut BenDecoder<'buf>,
+    last_key: Option<&'buf [u8]>,
+}
+
+impl<'a, 'buf, C> Dict<'buf, C> for &mut DictCursor<'a, 'buf> {
+    fn next_entry<T>(&mut self, ctx: &mut C) -> Result<Option<(&'buf [u8], T)>>
     where
-        T: Decode<'buf>,
+        T: Decode<'buf, C>,
     {
-        if self.peek_char()? == b'e' {
+        if self.dec.peek_char()? == b'e' {
             return Ok(None);
         }
 
-        let key = self.decode_bytes()?;
-        let value = T::decode(&mut **self)?;
+        let key = self.dec.decode_bytes(ctx)?;
+        self.check_key_order(key)?;
+
+        let value = T::decode(&mut *self.dec, ctx)?;
         Ok(Some((key, value)))
     }
+
+    fn get<T>(&mut self, key: &'buf [u8], ctx: &mut C) -> Result<Option<T>>
+    where
+        T: Decode<'buf, C>,
+    {
+        loop {
+            if self.dec.peek_char()? == b'e' {
+                return Ok(None);
+            }
+
+            let entry_key = self.dec.decode_bytes(ctx)?;
+            self.check_key_order(entry_key)?;
+            if entry_key != key {
+                self.dec.skip_value(ctx)?;
+                continue;
+            }
+
+            let value = T::decode(&mut *self.dec, ctx)?;
+            return Ok(Some(value));
+        }
+    }
 }
 
-impl<'a, 'buf> List<'buf> for &mut BenDecoder<'buf> {
-    fn next_element<T>(&mut self) -> Result<Option<T>>
+impl<'a, 'buf> DictCursor<'a, 'buf> {
+    fn check_key_order(&mut self, key: &'buf [u8]) -> Result<()> {
+        check_key_order(self.dec.strict, &mut self.last_key, key, self.dec.pos)
+    }
+}
+
+/// In strict mode, errors unless `key` sorts strictly after `*last_key`,
+/// then records it as the new `*last_key`. Shared by [`DictCursor`] (which
+/// tracks order across calls to `next_entry`/`get`) and [`BenDecoder::skip_value`]
+/// (which must enforce the same ordering inside a dict it's skipping over
+/// wholesale, e.g. while capturing a [`crate::de::Raw`] span).
+fn check_key_order<'buf>(
+    strict: bool,
+    last_key: &mut Option<&'buf [u8]>,
+    key: &'buf [u8],
+    pos: usize,
+) -> Result<()> {
+    if strict {
+        if let Some(last) = *last_key {
+            if key <= last {
+                return Err(Error::NonCanonical {
+                    reason: "dict keys are not in strictly ascending order",
+                    pos,
+                });
+            }
+        }
+        *last_key = Some(key);
+    }
+    Ok(())
+}
+
+impl<'buf, C> List<'buf, C> for &mut BenDecoder<'buf> {
+    fn next_element<T>(&mut self, ctx: &mut C) -> Result<Option<T>>
     where
-        T: Decode<'buf>,
+        T: Decode<'buf, C>,
     {
         if self.peek_char()? == b'e' {
             return Ok(None);
         }
 
-        let v = T::decode(&mut **self)?;
+        let v = T::decode(&mut **self, ctx)?;
         Ok(Some(v))
     }
 }
+
+/// Iterator over successive top-level values in a [`BenDecoder`]'s buffer,
+/// returned by [`BenDecoder::values`].
+pub struct Values<'a, 'buf, T> {
+    decoder: &'a mut BenDecoder<'buf>,
+    _marker: PhantomData<T>,
+}
+
+impl<'a, 'buf, T> Iterator for Values<'a, 'buf, T>
+where
+    T: Decode<'buf>,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.decoder.pos >= self.decoder.buf.len() {
+            return None;
+        }
+
+        Some(T::decode(&mut *self.decoder, &mut ()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de::Raw;
+    use crate::value::Value;
+
+    fn decode_strict<T: Decode<'static>>(buf: &'static [u8]) -> Result<T> {
+        T::decode(&mut BenDecoder::new_strict(buf), &mut ())
+    }
+
+    #[test]
+    fn strict_rejects_leading_zero_integer() {
+        assert!(decode_strict::<i64>(b"i01e").is_err());
+    }
+
+    #[test]
+    fn non_strict_allows_leading_zero_integer() {
+        let v = i64::decode(&mut BenDecoder::new(b"i01e"), &mut ()).unwrap();
+        assert_eq!(v, 1);
+    }
+
+    #[test]
+    fn strict_rejects_negative_zero() {
+        assert!(decode_strict::<i64>(b"i-0e").is_err());
+    }
+
+    #[test]
+    fn strict_rejects_leading_zero_length() {
+        assert!(decode_strict::<&[u8]>(b"01:a").is_err());
+    }
+
+    #[test]
+    fn strict_rejects_out_of_order_keys() {
+        assert!(decode_strict::<Value>(b"d1:bi1e1:ai2ee").is_err());
+    }
+
+    #[test]
+    fn strict_rejects_duplicate_keys() {
+        assert!(decode_strict::<Value>(b"d1:ai1e1:ai2ee").is_err());
+    }
+
+    #[test]
+    fn strict_decode_raw_rejects_out_of_order_keys_in_captured_span() {
+        assert!(decode_strict::<Raw>(b"d1:bi1e1:ai2ee").is_err());
+    }
+
+    #[test]
+    fn strict_decode_raw_accepts_ascending_keys_in_captured_span() {
+        let raw = decode_strict::<Raw>(b"d1:ai1e1:bi2ee").unwrap();
+        assert_eq!(raw.0, b"d1:ai1e1:bi2ee");
+    }
+
+    #[test]
+    fn strict_accepts_ascending_keys() {
+        assert!(decode_strict::<Value>(b"d1:ai1e1:bi2ee").is_ok());
+    }
+
+    #[test]
+    fn dict_get_skips_non_matching_entries_before_the_match() {
+        struct GetB;
+
+        impl<'buf> Visitor<'buf, ()> for GetB {
+            type Value = Option<i64>;
+
+            fn visit_dict<A>(self, mut v: A, ctx: &mut ()) -> Result<Self::Value>
+            where
+                A: Dict<'buf, ()>,
+            {
+                v.get(b"b", ctx)
+            }
+        }
+
+        let mut dec = BenDecoder::new(b"d1:ai1e1:bi2ee");
+        let v = (&mut dec).decode_dict(GetB, &mut ()).unwrap();
+        assert_eq!(v, Some(2));
+    }
+
+    #[test]
+    fn values_decodes_successive_top_level_values() {
+        let mut dec = BenDecoder::new(b"i1ei2ei3e");
+        let got: Vec<i64> = dec.values().map(Result::unwrap).collect();
+        assert_eq!(got, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn values_stops_once_the_buffer_is_exactly_consumed() {
+        let mut dec = BenDecoder::new(b"i1e");
+        let mut values = dec.values::<i64>();
+        assert_eq!(values.next().unwrap().unwrap(), 1);
+        assert!(values.next().is_none());
+    }
+
+    #[test]
+    fn values_surfaces_the_decode_error_for_a_truncated_trailing_value() {
+        let mut dec = BenDecoder::new(b"i1ei2");
+        let mut values = dec.values::<i64>();
+        assert_eq!(values.next().unwrap().unwrap(), 1);
+        assert!(matches!(values.next(), Some(Err(Error::Eof))));
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::io::Write;
+
+use crate::err::{Error, Result};
+
+pub trait Encode {
+    fn encode<W>(&self, encoder: &mut BenEncoder<W>) -> Result<()>
+    where
+        W: Write;
+}
+
+pub fn encode<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Encode,
+{
+    let mut encoder = BenEncoder::new(Vec::new());
+    value.encode(&mut encoder)?;
+    Ok(encoder.into_inner())
+}
+
+pub struct BenEncoder<W> {
+    writer: W,
+}
+
+impl<W> BenEncoder<W>
+where
+    W: Write,
+{
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    pub fn encode_int(&mut self, v: i64) -> Result<()> {
+        write!(self.writer, "i{}e", v).map_err(Error::Io)
+    }
+
+    pub fn encode_bytes(&mut self, v: &[u8]) -> Result<()> {
+        write!(self.writer, "{}:", v.len()).map_err(Error::Io)?;
+        self.writer.write_all(v).map_err(Error::Io)
+    }
+
+    pub fn encode_list<F>(&mut self, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut ListEncoder<W>) -> Result<()>,
+    {
+        self.writer.write_all(b"l").map_err(Error::Io)?;
+        f(&mut ListEncoder { enc: self })?;
+        self.writer.write_all(b"e").map_err(Error::Io)
+    }
+
+    pub fn encode_dict<F>(&mut self, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut DictEncoder<W>) -> Result<()>,
+    {
+        self.writer.write_all(b"d").map_err(Error::Io)?;
+        f(&mut DictEncoder {
+            enc: self,
+            last_key: None,
+        })?;
+        self.writer.write_all(b"e").map_err(Error::Io)
+    }
+}
+
+pub struct ListEncoder<'a, W> {
+    enc: &'a mut BenEncoder<W>,
+}
+
+impl<'a, W> ListEncoder<'a, W>
+where
+    W: Write,
+{
+    pub fn encode_element<T>(&mut self, v: &T) -> Result<()>
+    where
+        T: Encode,
+    {
+        v.encode(self.enc)
+    }
+}
+
+pub struct DictEncoder<'a, W> {
+    enc: &'a mut BenEncoder<W>,
+    last_key: Option<Vec<u8>>,
+}
+
+impl<'a, W> DictEncoder<'a, W>
+where
+    W: Write,
+{
+    /// Encodes one entry. Callers must supply keys in ascending
+    /// lexicographic order, matching canonical bencode; out-of-order keys
+    /// are rejected rather than silently re-sorted.
+    pub fn encode_entry<T>(&mut self, key: &[u8], v: &T) -> Result<()>
+    where
+        T: Encode,
+    {
+        if let Some(last) = &self.last_key {
+            if key <= last.as_slice() {
+                return Err(Error::Type {
+                    reason: "Dict keys must be supplied in ascending order",
+                });
+            }
+        }
+        self.last_key = Some(key.to_vec());
+
+        self.enc.encode_bytes(key)?;
+        v.encode(self.enc)
+    }
+}
+
+impl Encode for i64 {
+    fn encode<W>(&self, encoder: &mut BenEncoder<W>) -> Result<()>
+    where
+        W: Write,
+    {
+        encoder.encode_int(*self)
+    }
+}
+
+impl Encode for [u8] {
+    fn encode<W>(&self, encoder: &mut BenEncoder<W>) -> Result<()>
+    where
+        W: Write,
+    {
+        encoder.encode_bytes(self)
+    }
+}
+
+impl Encode for str {
+    fn encode<W>(&self, encoder: &mut BenEncoder<W>) -> Result<()>
+    where
+        W: Write,
+    {
+        encoder.encode_bytes(self.as_bytes())
+    }
+}
+
+impl<T> Encode for &T
+where
+    T: Encode + ?Sized,
+{
+    fn encode<W>(&self, encoder: &mut BenEncoder<W>) -> Result<()>
+    where
+        W: Write,
+    {
+        (**self).encode(encoder)
+    }
+}
+
+////////////////// Impls //////////////////
+
+macro_rules! tuple_impl {
+    ($($t:ident),* ) => {
+        #[allow(non_snake_case)]
+        impl<$( $t ),*> Encode for ($( $t ),*)
+        where
+            $( $t: Encode ),*
+        {
+            #[allow(unused)]
+            fn encode<W>(&self, encoder: &mut BenEncoder<W>) -> Result<()>
+            where
+                W: Write,
+            {
+                let ($( $t ),*) = self;
+                encoder.encode_list(|list| {
+                    $( list.encode_element($t)?; )*
+                    Ok(())
+                })
+            }
+        }
+    }
+}
+
+tuple_impl!();
+tuple_impl!(T0, T1);
+tuple_impl!(T0, T1, T2);
+tuple_impl!(T0, T1, T2, T3);
+tuple_impl!(T0, T1, T2, T3, T4);
+tuple_impl!(T0, T1, T2, T3, T4, T5);
+tuple_impl!(T0, T1, T2, T3, T4, T5, T6);
+tuple_impl!(T0, T1, T2, T3, T4, T5, T6, T7);
+tuple_impl!(T0, T1, T2, T3, T4, T5, T6, T7, T8);
+tuple_impl!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9);
+tuple_impl!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+tuple_impl!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
+tuple_impl!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
+tuple_impl!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13);
+tuple_impl!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14);
+tuple_impl!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15);
+
+macro_rules! array_impl {
+    ($len:literal) => {
+        impl<T> Encode for [T; $len]
+        where
+            T: Encode,
+        {
+            fn encode<W>(&self, encoder: &mut BenEncoder<W>) -> Result<()>
+            where
+                W: Write,
+            {
+                encoder.encode_list(|list| {
+                    for v in self {
+                        list.encode_element(v)?;
+                    }
+                    Ok(())
+                })
+            }
+        }
+    };
+}
+
+array_impl!(1);
+array_impl!(2);
+array_impl!(3);
+array_impl!(4);
+array_impl!(5);
+array_impl!(6);
+array_impl!(7);
+array_impl!(8);
+array_impl!(9);
+array_impl!(10);
+array_impl!(11);
+array_impl!(12);
+array_impl!(13);
+array_impl!(14);
+array_impl!(15);
+array_impl!(16);
+array_impl!(17);
+array_impl!(18);
+array_impl!(19);
+array_impl!(20);
+array_impl!(21);
+array_impl!(22);
+array_impl!(23);
+array_impl!(24);
+array_impl!(25);
+array_impl!(26);
+array_impl!(27);
+array_impl!(28);
+array_impl!(29);
+array_impl!(30);
+array_impl!(31);
+array_impl!(32);
+
+macro_rules! list_impl {
+    ($ty:ident $(+ $bounds:ident )* ) => {
+        impl<T> Encode for $ty<T>
+        where
+            T: Encode $( + $bounds )*,
+        {
+            fn encode<W>(&self, encoder: &mut BenEncoder<W>) -> Result<()>
+            where
+                W: Write,
+            {
+                encoder.encode_list(|list| {
+                    for v in self {
+                        list.encode_element(v)?;
+                    }
+                    Ok(())
+                })
+            }
+        }
+    }
+}
+
+list_impl!(Vec);
+list_impl!(VecDeque);
+list_impl!(HashSet + Hash + Eq);
+list_impl!(BTreeSet + Ord);
+
+impl<T> Encode for BTreeMap<&[u8], T>
+where
+    T: Encode,
+{
+    fn encode<W>(&self, encoder: &mut BenEncoder<W>) -> Result<()>
+    where
+        W: Write,
+    {
+        // `BTreeMap` already iterates in ascending key order, matching
+        // canonical bencode.
+        encoder.encode_dict(|dict| {
+            for (k, v) in self {
+                dict.encode_entry(k, v)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl<T> Encode for HashMap<&[u8], T>
+where
+    T: Encode,
+{
+    fn encode<W>(&self, encoder: &mut BenEncoder<W>) -> Result<()>
+    where
+        W: Write,
+    {
+        // `HashMap` has no defined iteration order, so keys are sorted
+        // before encoding to produce canonical output.
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_by_key(|(k, _)| *k);
+
+        encoder.encode_dict(|dict| {
+            for (k, v) in entries {
+                dict.encode_entry(k, v)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn btree_map_encodes_as_a_dict_in_ascending_key_order() {
+        let mut dict = BTreeMap::new();
+        dict.insert(&b"a"[..], 1i64);
+        dict.insert(&b"b"[..], 2i64);
+
+        let buf = encode(&dict).unwrap();
+        assert_eq!(buf, b"d1:ai1e1:bi2ee");
+    }
+
+    #[test]
+    fn hash_map_keys_are_sorted_into_canonical_ascending_order() {
+        let mut map = HashMap::new();
+        map.insert(&b"zebra"[..], 1i64);
+        map.insert(&b"apple"[..], 2i64);
+        map.insert(&b"mango"[..], 3i64);
+
+        let buf = encode(&map).unwrap();
+        assert_eq!(buf, b"d5:applei2e5:mangoi3e5:zebrai1ee");
+    }
+}
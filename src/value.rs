@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+
+use crate::de::{Decode, Decoder, Dict, List, Visitor};
+use crate::err::Result;
+
+/// A dynamically-typed bencode value, for decoding a document whose shape
+/// isn't known ahead of time (e.g. an arbitrary torrent file or DHT message).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value<'buf> {
+    Int(i64),
+    Bytes(&'buf [u8]),
+    List(Vec<Value<'buf>>),
+    Dict(BTreeMap<&'buf [u8], Value<'buf>>),
+}
+
+impl<'buf> Value<'buf> {
+    /// Returns the inner integer, or `None` if this isn't [`Value::Int`].
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Value::Int(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns the inner byte string, or `None` if this isn't [`Value::Bytes`].
+    pub fn as_bytes(&self) -> Option<&'buf [u8]> {
+        match self {
+            Value::Bytes(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Looks up `key` in this value, or `None` if this isn't [`Value::Dict`]
+    /// or the key isn't present.
+    pub fn get(&self, key: &[u8]) -> Option<&Value<'buf>> {
+        match self {
+            Value::Dict(v) => v.get(key),
+            _ => None,
+        }
+    }
+
+    /// Returns the element at `index`, or `None` if this isn't [`Value::List`]
+    /// or the index is out of bounds.
+    pub fn index(&self, index: usize) -> Option<&Value<'buf>> {
+        match self {
+            Value::List(v) => v.get(index),
+            _ => None,
+        }
+    }
+}
+
+impl<'buf, C> Decode<'buf, C> for Value<'buf> {
+    fn decode<D>(decoder: D, ctx: &mut C) -> Result<Self>
+    where
+        D: Decoder<'buf, C>,
+    {
+        decoder.decode_any(ValueVisitor, ctx)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'buf, C> Visitor<'buf, C> for ValueVisitor {
+    type Value = Value<'buf>;
+
+    fn visit_dict<A>(self, mut v: A, ctx: &mut C) -> Result<Self::Value>
+    where
+        A: Dict<'buf, C>,
+    {
+        let mut out = BTreeMap::new();
+        while let Some((k, v)) = v.next_entry(ctx)? {
+            out.insert(k, v);
+        }
+        Ok(Value::Dict(out))
+    }
+
+    fn visit_list<A>(self, mut v: A, ctx: &mut C) -> Result<Self::Value>
+    where
+        A: List<'buf, C>,
+    {
+        let mut out = Vec::new();
+        while let Some(v) = v.next_element(ctx)? {
+            out.push(v);
+        }
+        Ok(Value::List(out))
+    }
+
+    fn visit_bytes(self, v: &'buf [u8]) -> Result<Self::Value> {
+        Ok(Value::Bytes(v))
+    }
+
+    fn visit_int(self, v: i64) -> Result<Self::Value> {
+        Ok(Value::Int(v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn accessors_walk_a_nested_document() {
+        let v: Value = parse(b"d4:name3:foo5:filesld4:name1:a6:lengthi1eee5:totali1ee").unwrap();
+
+        assert_eq!(v.get(b"name").unwrap().as_bytes(), Some(&b"foo"[..]));
+        assert_eq!(v.get(b"total").unwrap().as_int(), Some(1));
+
+        let files = v.get(b"files").unwrap();
+        let first_file = files.index(0).unwrap();
+        assert_eq!(first_file.get(b"name").unwrap().as_bytes(), Some(&b"a"[..]));
+        assert_eq!(first_file.get(b"length").unwrap().as_int(), Some(1));
+
+        assert!(files.index(1).is_none());
+        assert!(v.get(b"missing").is_none());
+        assert!(v.as_int().is_none());
+    }
+}